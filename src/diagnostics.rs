@@ -0,0 +1,53 @@
+use super::settings::Settings;
+use super::trigger::Triggers;
+use super::variables;
+
+//More segments than this are not looked for
+const MAX_SEGMENTS: u64 = 1024;
+
+fn segment_count() -> u64 {
+    (0..MAX_SEGMENTS)
+        .find(|&index| asr::timer::segment_splitted(index).is_none())
+        .unwrap_or(MAX_SEGMENTS)
+}
+
+//Compares the enabled triggers with the segments of the loaded splits, logs which trigger
+//ends which segment when they differ and shows the difference on the layout
+pub fn check_segments(settings: &Settings) {
+    let segments = segment_count();
+    let enabled = Triggers::ALL
+        .iter()
+        .filter(|trigger| trigger.is_enabled(settings))
+        .count() as u64;
+
+    if enabled == segments {
+        variables::set("Splits Warning", format_args!(""));
+        return;
+    }
+
+    asr::print_limited::<1024>(&format_args!(
+        "Splits mismatch: {} splits are enabled but the splits have {} segments",
+        enabled, segments
+    ));
+    for index in 0..enabled.max(segments) {
+        match Triggers::nth_enabled(index as usize, settings) {
+            Some(trigger) if index < segments => asr::print_limited::<1024>(&format_args!(
+                "Segment {}: {}",
+                index + 1,
+                trigger.name()
+            )),
+            Some(trigger) => asr::print_limited::<1024>(&format_args!(
+                "No segment: {} will never split",
+                trigger.name()
+            )),
+            None => asr::print_limited::<1024>(&format_args!(
+                "Segment {}: no split enabled, the run cannot finish",
+                index + 1
+            )),
+        }
+    }
+    variables::set(
+        "Splits Warning",
+        format_args!("{} splits enabled, {} segments", enabled, segments),
+    );
+}
@@ -0,0 +1,110 @@
+use super::state::State;
+use super::trigger::Triggers;
+use super::variables;
+
+//Downwards speed in units per second at which the team counts as falling
+const FALLING_SPEED: f64 = 1200f64;
+//Height a fall has to lose before it counts, smaller drops are jumps and ledges
+const FALL_HEIGHT: f64 = 800f64;
+
+#[derive(Clone, Copy, Default)]
+struct Falls {
+    count: u32,
+    biggest: f64,
+    height_lost: f64,
+}
+
+impl Falls {
+    fn add(&mut self, height: f64) {
+        self.count += 1;
+        self.biggest = self.biggest.max(height);
+        self.height_lost += height;
+    }
+}
+
+//Falls of the current run, for the whole run and per area, indexed by the main trigger that
+//ends the area
+pub struct FallStats {
+    run: Falls,
+    areas: [Falls; Triggers::COUNT],
+    //Highest point since the team last landed, a fall is measured from there
+    peak: f64,
+    falling: bool,
+}
+
+impl FallStats {
+    pub fn new() -> FallStats {
+        FallStats {
+            run: Falls::default(),
+            areas: [Falls::default(); Triggers::COUNT],
+            peak: 0f64,
+            falling: false,
+        }
+    }
+
+    pub fn start_run(&mut self, state: &State) {
+        *self = FallStats::new();
+        self.peak = state.position_z;
+    }
+
+    //The area the team is climbing through at the given height
+    fn area(height: f64) -> Triggers {
+        Triggers::ALL
+            .iter()
+            .copied()
+            .find(|trigger| trigger.is_main() && trigger.floor() > height)
+            .unwrap_or(Triggers::TheSun)
+    }
+
+    pub fn update(&mut self, state: &State, old_state: &State) {
+        if !state.moved_from(old_state) {
+            //Loading in or out of the tower is not a fall, measure from wherever the team is now
+            self.falling = false;
+            self.peak = state.position_z;
+        } else if state.velocity_z <= -FALLING_SPEED {
+            self.falling = true;
+        } else if self.falling && state.velocity_z > -FALLING_SPEED / 4f64 {
+            //Landed, count it if enough height was lost
+            self.falling = false;
+            let height = self.peak - state.position_z;
+            if height >= FALL_HEIGHT {
+                self.run.add(height);
+                self.areas[FallStats::area(self.peak) as usize].add(height);
+            }
+            self.peak = state.position_z;
+        } else if !self.falling {
+            //Follow slow descents so climbing down to a ledge does not count towards a fall
+            self.peak = self
+                .peak
+                .max(state.position_z)
+                .min(state.position_z + FALL_HEIGHT);
+        }
+
+        let area = self.areas[FallStats::area(state.position_z) as usize];
+        variables::set("Falls", format_args!("{}", self.run.count));
+        variables::set("Biggest Fall", format_args!("{:.0}", self.run.biggest));
+        variables::set("Height Lost", format_args!("{:.0}", self.run.height_lost));
+        variables::set("Area Falls", format_args!("{}", area.count));
+        variables::set("Area Biggest Fall", format_args!("{:.0}", area.biggest));
+        variables::set("Area Height Lost", format_args!("{:.0}", area.height_lost));
+    }
+
+    pub fn log_summary(&self) {
+        asr::print_limited::<1024>(&format_args!(
+            "Falls: {}, biggest fall: {:.0}, height lost: {:.0}",
+            self.run.count, self.run.biggest, self.run.height_lost
+        ));
+        for trigger in Triggers::ALL.iter().filter(|trigger| trigger.is_main()) {
+            let falls = self.areas[*trigger as usize];
+            if falls.count != 0 {
+                asr::print_limited::<1024>(&format_args!(
+                    "{}: {} falls, biggest fall: {:.0}, height lost: {:.0}",
+                    trigger.name(),
+                    falls.count,
+                    falls.biggest,
+                    falls.height_lost
+                ));
+            }
+        }
+    }
+}
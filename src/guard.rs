@@ -0,0 +1,42 @@
+use super::settings::Settings;
+use super::trigger::Triggers;
+
+//Minimum in-game seconds between two splits
+const MINIMUM_SEGMENT_TIME: i64 = 2;
+
+//Holds back splits that come sooner after the previous one than any segment takes, a glitched
+//read or teleport would otherwise split far too early
+#[derive(Default)]
+pub struct SplitGuard {
+    last_split: i64,
+    suspicious: Option<Triggers>,
+}
+
+impl SplitGuard {
+    pub fn start_run(&mut self, game_time: i64) {
+        self.last_split = game_time;
+        self.suspicious = None;
+    }
+
+    pub fn record_split(&mut self, game_time: i64) {
+        self.start_run(game_time);
+    }
+
+    pub fn allows(&mut self, trigger: &Triggers, game_time: i64, settings: &Settings) -> bool {
+        if !settings.minimum_split_time {
+            return true;
+        }
+        if game_time - self.last_split < MINIMUM_SEGMENT_TIME {
+            if self.suspicious != Some(*trigger) {
+                asr::print_limited::<1024>(&format_args!(
+                    "Suspicious split at {} {}s after the previous one held back",
+                    trigger.name(),
+                    game_time - self.last_split
+                ));
+                self.suspicious = Some(*trigger);
+            }
+            return false;
+        }
+        true
+    }
+}
@@ -0,0 +1,42 @@
+use super::trigger::Triggers;
+
+//Extra height around each trigger so that exit activations still see the player leave
+const MARGIN: f64 = 100f64;
+
+#[derive(Clone, Copy)]
+struct Entry {
+    floor: f64,
+    ceiling: f64,
+    trigger: Triggers,
+}
+
+//Triggers sorted by the lowest height they can be reached at, so that only the few
+//triggers around the player's height have to be tested each tick
+pub struct TriggerIndex {
+    entries: [Entry; Triggers::COUNT],
+}
+
+impl TriggerIndex {
+    pub fn new() -> TriggerIndex {
+        let mut entries = Triggers::ALL.map(|trigger| Entry {
+            floor: trigger.floor() - MARGIN,
+            ceiling: trigger.ceiling() + MARGIN,
+            trigger,
+        });
+        entries.sort_unstable_by(|a, b| a.floor.total_cmp(&b.floor));
+        TriggerIndex { entries }
+    }
+
+    pub fn candidates(&self, height: f64) -> impl Iterator<Item = Triggers> + '_ {
+        let end = self.entries.partition_point(|entry| entry.floor <= height);
+        self.entries[..end]
+            .iter()
+            .filter(move |entry| entry.ceiling >= height)
+            .map(|entry| entry.trigger)
+    }
+
+    //Every trigger from the lowest to the highest
+    pub fn by_height(&self) -> impl Iterator<Item = Triggers> + '_ {
+        self.entries.iter().map(|entry| entry.trigger)
+    }
+}
@@ -1,12 +1,21 @@
 #![no_std]
 
+mod diagnostics;
+mod falls;
+mod guard;
+mod index;
 mod settings;
 mod state;
 mod trigger;
+mod variables;
 
-use settings::Settings;
-use state::State;
-use trigger::Triggers;
+use falls::FallStats;
+use guard::SplitGuard;
+use index::TriggerIndex;
+use settings::{Category, GameClosed, Settings};
+use state::{ResetConfirmation, StartConfirmation, State};
+use trigger::{TriggerTracker, Triggers};
+use variables::RunVariables;
 
 use asr::settings::gui::Gui;
 use asr::timer::TimerState;
@@ -15,6 +24,12 @@ use asr::{future::next_tick, Process};
 asr::async_main!(stable);
 asr::panic_handler!();
 
+pub const TICK_RATE: f64 = 120f64;
+//How far below the last split area the team has to fall before the split is undone
+const UNDO_HYSTERESIS: f64 = 2000f64;
+//Ticks in a row the team has to stay below it, so a single garbage read cannot undo a split
+const UNDO_CONFIRMATION_TICKS: u32 = 10;
+
 trait Square {
     fn square(&self) -> f64;
 }
@@ -25,19 +40,82 @@ impl Square for f64 {
     }
 }
 
+trait SquareRoot {
+    fn square_root(&self) -> f64;
+}
+
+impl SquareRoot for f64 {
+    //core has no square root without std, a few Newton steps from a guess made by halving the
+    //exponent bits are exact to the last bit
+    fn square_root(&self) -> f64 {
+        if *self <= 0f64 {
+            return 0f64;
+        }
+        let mut root = f64::from_bits((self.to_bits() >> 1) + (1023u64 << 51));
+        for _ in 0..4 {
+            root = (root + self / root) / 2f64;
+        }
+        root
+    }
+}
+
 async fn main() {
     let mut settings = Settings::register();
+    let index = TriggerIndex::new();
+    asr::set_tick_rate(TICK_RATE);
+    //In-game time at the start of the run, individual levels are timed from there
+    let mut timer_offset = 0i64;
+    let mut game_time = 0i64;
+    //Game time when the game closed mid-run, to pick it back up from on re-attach
+    let mut closed_game_time = None;
 
     loop {
         settings.update();
-        //Attempt to attach to process with the following name
-        let process = Process::wait_attach("ChainedTogether-Win64-Shipping").await;
+        //Attempt to attach to process with the following name, counting how long it was gone
+        let mut closed_ticks = 0u64;
+        let process = loop {
+            if let Some(process) = Process::attach("ChainedTogether-Win64-Shipping") {
+                break process;
+            }
+            //A run reset while the game is closed is not resumed
+            if asr::timer::state() == TimerState::NotRunning {
+                closed_game_time = None;
+            }
+            closed_ticks += 1;
+            next_tick().await;
+        };
         process
             .until_closes(async {
                 asr::print_message("Creating Initial State");
 
                 let mut current_state = State::default();
                 let mut current_trigger = Triggers::first_trigger(&settings);
+                let mut tracker = TriggerTracker::new();
+                let mut completed = [false; Triggers::COUNT];
+                let mut split_index = None;
+                let mut start_confirmation = StartConfirmation::default();
+                let mut reset_confirmation = ResetConfirmation::default();
+                let mut split_guard = SplitGuard::default();
+                let mut run_variables = RunVariables::default();
+                let mut fall_stats = FallStats::new();
+                let mut undo_ticks = 0u32;
+                let mut last_timer_state = asr::timer::state();
+                //A run that is still going when attaching was interrupted by the game closing
+                let mut resuming =
+                    [TimerState::Running, TimerState::Paused].contains(&asr::timer::state());
+
+                //Logic to bring back the splits done in any order before the game closed, only
+                //their count is known so the lowest ones are taken to be done
+                if resuming && settings.any_order_splits {
+                    let done = asr::timer::current_split_index().unwrap_or(0) as usize;
+                    for trigger in index
+                        .by_height()
+                        .filter(|trigger| trigger.is_enabled(&settings))
+                        .take(done)
+                    {
+                        completed[trigger as usize] = true;
+                    }
+                }
 
                 loop {
                     //Load each of the relevant values from memory in to variables
@@ -52,58 +130,288 @@ async fn main() {
                             continue;
                         }
                     };
-
-                    asr::timer::set_game_time(asr::time::Duration::new(
-                        current_state.timer as i64,
-                        0,
-                    ));
+                    current_state.update_velocity(&old_state);
 
                     #[cfg(debug_assertions)]
                     current_state.log();
 
+                    //The lobby and loading of the restarted game are part of the time it was gone
+                    if resuming {
+                        closed_ticks += 1;
+                    }
+
+                    //Logic to pick the run back up once the restarted game is in the session,
+                    //the split index already brought the route back
+                    if resuming && current_state.timer != 0 {
+                        asr::print_message("Resuming run");
+
+                        resuming = false;
+
+                        //Logic to continue game time from where it stopped if the restarted
+                        //game's timer lost time
+                        if let Some(closed_at) = closed_game_time.take() {
+                            let closed_for = match settings.game_closed {
+                                GameClosed::KeepRunning => (closed_ticks as f64 / TICK_RATE) as i64,
+                                _ => 0,
+                            };
+                            if current_state.timer as i64 - timer_offset < closed_at + closed_for {
+                                timer_offset =
+                                    current_state.timer as i64 - (closed_at + closed_for);
+                            }
+                        }
+                        if settings.game_closed == GameClosed::PauseGameTime {
+                            asr::timer::resume_game_time();
+                        }
+                    }
+
+                    //The restarted game's timer means nothing until it is back in the session
+                    if !resuming {
+                        game_time = current_state.timer as i64 - timer_offset;
+                        asr::timer::set_game_time(asr::time::Duration::new(game_time, 0));
+                    }
+
                     let unstarted_states = [
                         TimerState::NotRunning,
                         TimerState::Ended,
                         TimerState::Unknown,
                     ];
 
+                    let mut started = false;
+
                     //Logic to trigger timer start if necessary
+                    let should_start = start_confirmation.should_start(&current_state, &settings);
                     if unstarted_states.contains(timer_state)
-                        && current_state.should_start(&old_state)
+                        && !settings.practice_mode
+                        && settings.category != Category::IndividualLevel
+                        && should_start
                     {
                         #[cfg(debug_assertions)]
                         asr::print_message("Starting run!");
                         asr::timer::start();
+                        timer_offset = 0;
+                        started = true;
+                    }
+
+                    //Logic to start an individual level when the team leaves the area's entry,
+                    //these are played from checkpoints so the session's origin does not matter
+                    if unstarted_states.contains(timer_state)
+                        && !settings.practice_mode
+                        && settings.category == Category::IndividualLevel
+                        && current_state.should_start_level(
+                            &old_state,
+                            &settings.individual_level_area.end_trigger(),
+                        )
+                    {
+                        #[cfg(debug_assertions)]
+                        asr::print_message("Starting individual level!");
+                        asr::timer::start();
+                        timer_offset = current_state.timer as i64;
+                        started = true;
+                    }
+
+                    //Logic to restart practice each time the team gets back to its start
+                    if settings.practice_mode
+                        && current_state
+                            .should_restart_practice(&old_state, &settings.practice_start)
+                    {
+                        #[cfg(debug_assertions)]
+                        asr::print_message("Restarting practice!");
+
+                        if *timer_state != TimerState::NotRunning {
+                            asr::timer::reset();
+                        }
+                        asr::timer::start();
+                        timer_offset = current_state.timer as i64;
+                        started = true;
+                        current_trigger = Triggers::first_trigger(&settings);
+                        tracker.reset();
+                        completed = [false; Triggers::COUNT];
+                    }
+
+                    //Logic shared by every way of starting a run, including runs started by hand
+                    //in LiveSplit
+                    if started
+                        || (last_timer_state == TimerState::NotRunning
+                            && *timer_state == TimerState::Running)
+                    {
+                        reset_confirmation.start_run(&current_state);
+                        split_guard.start_run(0);
+                        run_variables.start_run(&current_state);
+                        fall_stats.start_run(&current_state);
+                    }
+
+                    //Logic to follow splits, skips and undos done by the runner in LiveSplit.
+                    //Only changes are followed so a split that LiveSplit has not applied yet
+                    //does not move the route back
+                    let timer_split_index = asr::timer::current_split_index();
+                    if timer_split_index != split_index {
+                        //Logic to compare the route with the splits when a run starts
+                        if split_index.is_none() {
+                            diagnostics::check_segments(&settings);
+                        }
+                        if let Some(index) =
+                            timer_split_index.filter(|_| !settings.any_order_splits)
+                        {
+                            current_trigger = Triggers::nth_enabled(index as usize, &settings);
+                        }
+                        split_index = timer_split_index;
+                    }
+
+                    //Logic to trigger splits in any order, each enabled trigger splits once
+                    if *timer_state == TimerState::Running && settings.any_order_splits {
+                        for trigger in index.candidates(current_state.position_z) {
+                            if trigger.is_enabled(&settings)
+                                && !completed[trigger as usize]
+                                && current_state.should_split(&trigger, &mut tracker, &settings)
+                            {
+                                if split_guard.allows(&trigger, game_time, &settings) {
+                                    #[cfg(debug_assertions)]
+                                    asr::print_limited::<1024>(&format_args!(
+                                        "Splitting at {}!",
+                                        trigger.name()
+                                    ));
+
+                                    asr::timer::split();
+                                    split_guard.record_split(game_time);
+                                    completed[trigger as usize] = true;
+                                } else {
+                                    tracker.hold(&trigger);
+                                }
+                            }
+                        }
                     }
 
                     //Logic to trigger splits
+                    if *timer_state == TimerState::Running && !settings.any_order_splits {
+                        if let Some(trigger) = current_trigger {
+                            if current_state.should_split(&trigger, &mut tracker, &settings) {
+                                if split_guard.allows(&trigger, game_time, &settings) {
+                                    #[cfg(debug_assertions)]
+                                    asr::print_limited::<1024>(&format_args!(
+                                        "Splitting at {}!",
+                                        trigger.name()
+                                    ));
+
+                                    asr::timer::split();
+                                    split_guard.record_split(game_time);
+                                    current_trigger = trigger.next_trigger(&settings);
+                                } else {
+                                    tracker.hold(&trigger);
+                                }
+                            } else if settings.skip_missed_splits {
+                                //Logic to skip the splits of triggers the route jumped past
+                                if let Some((missed, reached)) =
+                                    trigger.upcoming(&settings).enumerate().find(|(_, later)| {
+                                        current_state.should_split(later, &mut tracker, &settings)
+                                    })
+                                {
+                                    if split_guard.allows(&reached, game_time, &settings) {
+                                        #[cfg(debug_assertions)]
+                                        asr::print_limited::<1024>(&format_args!(
+                                            "Skipping {} splits!",
+                                            missed + 1
+                                        ));
+
+                                        for _ in 0..=missed {
+                                            asr::timer::skip_split();
+                                        }
+                                        asr::timer::split();
+                                        split_guard.record_split(game_time);
+                                        current_trigger = reached.next_trigger(&settings);
+                                    } else {
+                                        tracker.hold(&reached);
+                                    }
+                                }
+                            }
+                        }
+                    }
+
+                    //Logic to show the team's progress on the layout
+                    let next_trigger = if settings.any_order_splits {
+                        Triggers::ALL.iter().copied().find(|trigger| {
+                            trigger.is_enabled(&settings) && !completed[*trigger as usize]
+                        })
+                    } else {
+                        current_trigger
+                    };
+                    run_variables.update(&current_state, next_trigger);
+                    if *timer_state == TimerState::Running {
+                        fall_stats.update(&current_state, &old_state);
+                    }
+                    if *timer_state == TimerState::Ended && last_timer_state != TimerState::Ended {
+                        fall_stats.log_summary();
+                    }
+                    last_timer_state = *timer_state;
+
+                    //Logic to undo the last split when the team falls back below its area, loads
+                    //and the lobby of a resumed run read as the origin and are no fall
                     if *timer_state == TimerState::Running
-                        && current_state.should_split(&current_trigger)
+                        && !settings.any_order_splits
+                        && settings.undo_split_on_fall
+                        && !resuming
                     {
-                        #[cfg(debug_assertions)]
-                        asr::print_limited::<1024>(&format_args!("Splitting!",));
+                        if let Some(last) = Triggers::previous_trigger(current_trigger, &settings) {
+                            if current_state.moved_from(&old_state)
+                                && current_state.position_z < last.floor() - UNDO_HYSTERESIS
+                            {
+                                undo_ticks += 1;
+                            } else {
+                                undo_ticks = 0;
+                            }
+                            if undo_ticks >= UNDO_CONFIRMATION_TICKS {
+                                #[cfg(debug_assertions)]
+                                asr::print_message("Undoing split!");
 
-                        asr::timer::split();
-                        current_trigger = current_trigger.next_trigger(&settings);
+                                asr::timer::undo_split();
+                                current_trigger = Some(last);
+                                undo_ticks = 0;
+                            }
+                        }
                     }
 
-                    //Logic to trigger Resets
-                    if *timer_state != TimerState::NotRunning && current_state.should_reset() {
+                    //Logic to trigger Resets, finished runs are kept unless asked otherwise
+                    let should_reset = reset_confirmation.should_reset(&current_state, &settings);
+                    if *timer_state != TimerState::NotRunning
+                        && (*timer_state != TimerState::Ended || settings.reset_completed_runs)
+                        && settings.auto_reset
+                        && !resuming
+                        && should_reset
+                    {
                         #[cfg(debug_assertions)]
                         asr::print_message("Reseting Run");
 
                         asr::timer::reset();
                         current_trigger = Triggers::first_trigger(&settings);
+                        tracker.reset();
+                        completed = [false; Triggers::COUNT];
                     }
 
                     //Logic to reset current trigger to start so fresh splits can work
                     if *timer_state == TimerState::NotRunning {
+                        resuming = false;
+                        closed_game_time = None;
                         current_trigger = Triggers::first_trigger(&settings);
+                        tracker.reset();
+                        completed = [false; Triggers::COUNT];
                     }
 
                     next_tick().await;
                 }
             })
             .await;
+
+        //Logic for a game that closed mid-run
+        if [TimerState::Running, TimerState::Paused].contains(&asr::timer::state()) {
+            asr::print_message("Game closed mid-run");
+
+            match settings.game_closed {
+                GameClosed::PauseGameTime => asr::timer::pause_game_time(),
+                GameClosed::KeepRunning => {}
+                GameClosed::ResetRun => asr::timer::reset(),
+            }
+            if settings.game_closed != GameClosed::ResetRun {
+                closed_game_time = Some(game_time);
+            }
+        }
     }
 }
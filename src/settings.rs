@@ -1,7 +1,109 @@
+use super::trigger::Triggers;
+
 use asr::settings::gui::{Gui, Title};
 
+#[derive(Gui, Clone, Copy, PartialEq)]
+pub enum Category {
+    /// Custom (use the splits below)
+    #[default]
+    Custom,
+    /// Any% (main areas)
+    AnyPercent,
+    /// All Splits (main areas and misc splits)
+    AllSplits,
+    /// Individual Level (one area)
+    IndividualLevel,
+}
+
+#[derive(Gui, Clone, Copy, PartialEq)]
+pub enum Area {
+    /// Underworld
+    #[default]
+    Underworld,
+    /// Hell Cliffs
+    HellCliffs,
+    /// The Car Race
+    TheCarRace,
+    /// The Whispering Vault
+    TheWhisperingVault,
+    /// The Subway Station
+    TheSubwayStation,
+    /// The City
+    TheCity,
+    /// Over The Buildings
+    OverTheBuildings,
+    /// The Warehouse
+    TheWarehouse,
+    /// The Harbor
+    TheHarbor,
+    /// The Temple
+    TheTemple,
+    /// The Asian Shrine
+    TheAsianShrine,
+    /// The Deities
+    TheDeities,
+    /// The Garden
+    TheGarden,
+    /// The Sun
+    TheSun,
+}
+
+impl Area {
+    pub fn end_trigger(&self) -> Triggers {
+        match self {
+            Area::Underworld => Triggers::Underworld,
+            Area::HellCliffs => Triggers::HellCliffs,
+            Area::TheCarRace => Triggers::TheCarRace,
+            Area::TheWhisperingVault => Triggers::TheWhisperingVault,
+            Area::TheSubwayStation => Triggers::TheSubwayStation,
+            Area::TheCity => Triggers::TheCity,
+            Area::OverTheBuildings => Triggers::OverTheBuildings,
+            Area::TheWarehouse => Triggers::TheWarehouse,
+            Area::TheHarbor => Triggers::TheHarbor,
+            Area::TheTemple => Triggers::TheTemple,
+            Area::TheAsianShrine => Triggers::TheAsianShrine,
+            Area::TheDeities => Triggers::TheDeities,
+            Area::TheGarden => Triggers::TheGarden,
+            Area::TheSun => Triggers::TheSun,
+        }
+    }
+}
+
+#[derive(Gui, Clone, Copy, PartialEq)]
+pub enum GameClosed {
+    /// Pause game time until the game is back
+    #[default]
+    PauseGameTime,
+    /// Keep game time running
+    KeepRunning,
+    /// Reset the run
+    ResetRun,
+}
+
+#[derive(Gui, Clone, Copy, PartialEq)]
+pub enum StartCondition {
+    /// In-game timer starts
+    #[default]
+    InGameTimer,
+    /// First movement away from the spawn point
+    FirstMovement,
+    /// Leaving the starting area
+    LeaveStartArea,
+    /// Loading from the lobby into the game
+    LevelTransition,
+}
+
 #[derive(Gui)]
 pub struct Settings {
+    pub category: Category,
+    pub individual_level_area: Area,
+
+    pub _practice: Title,
+    #[default = false]
+    pub practice_mode: bool,
+    pub practice_start: Triggers,
+    pub practice_end: Triggers,
+
     pub _default_splits: Title,
     #[default = true]
     pub toggle_underworld: bool,
@@ -77,4 +179,34 @@ pub struct Settings {
     pub toggle_kiosk: bool,
     #[default = false]
     pub toggle_carriage: bool,
+
+    pub _split_options: Title,
+    #[default = false]
+    pub any_order_splits: bool,
+    #[default = false]
+    pub skip_missed_splits: bool,
+    #[default = false]
+    pub undo_split_on_fall: bool,
+    #[default = false]
+    pub minimum_split_time: bool,
+    #[default = true]
+    pub split_on_ending: bool,
+
+    pub _run_options: Title,
+    pub start_condition: StartCondition,
+    #[default = false]
+    pub start_from_checkpoints: bool,
+    #[default = true]
+    pub auto_reset: bool,
+    #[default = true]
+    pub reset_on_main_menu: bool,
+    #[default = false]
+    pub reset_on_new_session: bool,
+    #[default = false]
+    pub reset_on_timer_rollback: bool,
+    #[default = true]
+    pub reset_on_spawn_point: bool,
+    #[default = false]
+    pub reset_completed_runs: bool,
+    pub game_closed: GameClosed,
 }
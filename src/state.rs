@@ -1,13 +1,32 @@
-use super::trigger::Triggers;
+use super::settings::{Settings, StartCondition};
+use super::trigger::{TriggerTracker, Triggers, SPAWN_AREA, SPAWN_POINT};
 use super::Square;
+use super::TICK_RATE;
 use asr::Process;
 
+//Weight of the newest sample when smoothing velocity, position reads are jittery per tick
+const VELOCITY_SMOOTHING: f64 = 0.25;
+//Speed in units per second no movement reaches, faster position changes are loads or teleports
+const MAX_SPEED: f64 = 20000f64;
+//Ticks in a row a start condition has to hold, so a single garbage read cannot start a run
+const START_CONFIRMATION_TICKS: u32 = 3;
+//How far the player has to move away from the spawn point to count as moving
+const MOVEMENT_DISTANCE: f64 = 50f64;
+//Ticks in a row a reset condition has to hold, so a single garbage read cannot reset a run
+const RESET_CONFIRMATION_TICKS: u32 = 10;
+//Seconds the in-game timer has to go back by to count as rolled back
+const ROLLBACK_TOLERANCE: u32 = 2;
+
 #[derive(Default, Clone)]
 pub struct State {
     pub position_x: f64,
     pub position_y: f64,
     pub position_z: f64,
 
+    pub velocity_x: f64,
+    pub velocity_y: f64,
+    pub velocity_z: f64,
+
     pub timer: u32,
 }
 
@@ -48,9 +67,37 @@ impl State {
             position_y,
             position_z,
             timer,
+            ..Default::default()
         }))
     }
 
+    //Whether the position changed in a way the player can move, not by loading in or out of the
+    //tower where the position reads as the origin
+    pub fn moved_from(&self, old_state: &State) -> bool {
+        !self.is_at_origin()
+            && !old_state.is_at_origin()
+            && self.distance_squared_to((
+                old_state.position_x,
+                old_state.position_y,
+                old_state.position_z,
+            )) <= (MAX_SPEED / TICK_RATE).square()
+    }
+
+    //Velocity starts over from rest after a jump in position
+    pub fn update_velocity(&mut self, old_state: &State) {
+        if !self.moved_from(old_state) {
+            return;
+        }
+        let smooth = |old: f64, delta: f64| old + (delta * TICK_RATE - old) * VELOCITY_SMOOTHING;
+        self.velocity_x = smooth(old_state.velocity_x, self.position_x - old_state.position_x);
+        self.velocity_y = smooth(old_state.velocity_y, self.position_y - old_state.position_y);
+        self.velocity_z = smooth(old_state.velocity_z, self.position_z - old_state.position_z);
+    }
+
+    pub fn horizontal_speed_squared(&self) -> f64 {
+        self.velocity_x.square() + self.velocity_y.square()
+    }
+
     pub fn log(&self) {
         asr::print_limited::<1024>(&format_args!(
             "({:.2?}, {:.2?}, {:.2?}) ({:?}:{:02x})",
@@ -62,20 +109,157 @@ impl State {
         ));
     }
 
-    pub fn should_start(&self, old_state: &State) -> bool {
-        old_state.timer == 0 && self.timer != 0
+    fn distance_squared_to(&self, (x, y, z): (f64, f64, f64)) -> f64 {
+        (self.position_x - x).square()
+            + (self.position_y - y).square()
+            + (self.position_z - z).square()
+    }
+
+    fn is_at_origin(&self) -> bool {
+        self.position_x == 0f64 && self.position_y == 0f64 && self.position_z == 0f64
+    }
+
+    //Whether the game is still in the state a run starts from
+    fn is_before_start(&self, condition: StartCondition) -> bool {
+        match condition {
+            StartCondition::InGameTimer => self.timer == 0,
+            StartCondition::FirstMovement => self.distance_squared_to(SPAWN_POINT) <= 1f64,
+            StartCondition::LeaveStartArea => SPAWN_AREA.should_split(self),
+            //There is no player in the tower while in the lobby, its position reads as the origin
+            StartCondition::LevelTransition => self.is_at_origin(),
+        }
+    }
+
+    //Going back to the lobby reads as the origin, which is away from the spawn but no start
+    fn is_started(&self, condition: StartCondition) -> bool {
+        match condition {
+            StartCondition::InGameTimer => self.timer != 0,
+            StartCondition::FirstMovement => {
+                !self.is_at_origin()
+                    && self.distance_squared_to(SPAWN_POINT) > MOVEMENT_DISTANCE.square()
+            }
+            StartCondition::LeaveStartArea => {
+                !self.is_at_origin() && !SPAWN_AREA.should_split(self)
+            }
+            StartCondition::LevelTransition => SPAWN_AREA.should_split(self),
+        }
+    }
+
+    //A load leaves the area's entry without the team moving
+    pub fn should_start_level(&self, old_state: &State, area: &Triggers) -> bool {
+        self.moved_from(old_state) && area.in_area_entry(old_state) && !area.in_area_entry(self)
+    }
+
+    //A garbage read outside the start would otherwise count as entering it again
+    pub fn should_restart_practice(&self, old_state: &State, start: &Triggers) -> bool {
+        self.moved_from(old_state) && !start.contains(old_state) && start.contains(self)
+    }
+
+    pub fn should_split(
+        &self,
+        current_trigger: &Triggers,
+        tracker: &mut TriggerTracker,
+        settings: &Settings,
+    ) -> bool {
+        tracker.fired(current_trigger, self, settings)
     }
 
-    pub fn should_split(&mut self, current_trigger: &Triggers) -> bool {
-        current_trigger.should_split(self)
+    //`peak_timer` is the highest in-game timer seen since the run started
+    fn should_reset(&self, peak_timer: u32, settings: &Settings) -> bool {
+        (settings.reset_on_main_menu && self.timer == 0 && self.is_at_origin())
+            || (settings.reset_on_new_session && self.timer == 0 && peak_timer != 0)
+            || (settings.reset_on_timer_rollback
+                && self.timer != 0
+                && self.timer.saturating_add(ROLLBACK_TOLERANCE) < peak_timer)
+            || (settings.reset_on_spawn_point
+                && self.timer == 0
+                && self.distance_squared_to(SPAWN_POINT) <= 1f64)
     }
+}
+
+//Starts a run once the game has been seen before the start and the start condition then held
+//for several ticks in a row
+#[derive(Default)]
+pub struct StartConfirmation {
+    armed: bool,
+    ticks: u32,
+    last_timer: u32,
+    //Whether the current session was loaded from a checkpoint instead of the tower's start
+    from_checkpoint: bool,
+}
+
+impl StartConfirmation {
+    pub fn should_start(&mut self, state: &State, settings: &Settings) -> bool {
+        let condition = settings.start_condition;
+
+        if self.last_timer == 0 && state.timer != 0 {
+            self.from_checkpoint = !SPAWN_AREA.should_split(state);
+            if self.from_checkpoint {
+                asr::print_message("Session continues from a checkpoint");
+            }
+        }
+        self.last_timer = state.timer;
+        //Start conditions other than the timer can hold before the new session's timer ticks,
+        //a team at the tower's start is not in the previous session's checkpoint
+        if self.armed && SPAWN_AREA.should_split(state) {
+            self.from_checkpoint = false;
+        }
+
+        if state.is_before_start(condition) {
+            self.armed = true;
+            self.ticks = 0;
+            return false;
+        }
+        if !self.armed {
+            return false;
+        }
+
+        if state.is_started(condition) {
+            self.ticks += 1;
+        } else {
+            self.ticks = 0;
+        }
+        if self.ticks < START_CONFIRMATION_TICKS {
+            return false;
+        }
+        self.armed = false;
+        self.ticks = 0;
+        !self.from_checkpoint || settings.start_from_checkpoints
+    }
+}
+
+//Resets a run once one of the enabled reset conditions held for several ticks in a row
+#[derive(Default)]
+pub struct ResetConfirmation {
+    peak_timer: u32,
+    last_timer: u32,
+    ticks: u32,
+}
+
+impl ResetConfirmation {
+    pub fn start_run(&mut self, state: &State) {
+        self.peak_timer = state.timer;
+        self.last_timer = state.timer;
+        self.ticks = 0;
+    }
+
+    pub fn should_reset(&mut self, state: &State, settings: &Settings) -> bool {
+        if state.should_reset(self.peak_timer, settings) {
+            self.ticks += 1;
+        } else {
+            self.ticks = 0;
+        }
+        //Only a timer read that agrees with the one before raises the peak, a single garbage
+        //read would otherwise count every later tick as rolled back
+        if state.timer.abs_diff(self.last_timer) <= ROLLBACK_TOLERANCE {
+            self.peak_timer = self.peak_timer.max(state.timer);
+        }
+        self.last_timer = state.timer;
 
-    pub fn should_reset(&self) -> bool {
-        self.timer == 0
-            && ((((self.position_x - 66649.54).square()
-                + (self.position_y + 7418.37).square()
-                + (self.position_z - 3118.52).square())
-                <= 1f64)
-                || (self.position_x == 0f64 && self.position_y == 0f64 && self.position_z == 0f64))
+        if self.ticks < RESET_CONFIRMATION_TICKS {
+            return false;
+        }
+        self.start_run(state);
+        true
     }
 }
@@ -1,66 +1,81 @@
-use super::settings::Settings;
+use super::settings::{Category, Settings};
 use super::state::State;
 
-use super::Square;
+use asr::settings::gui::Gui;
 
+use super::TICK_RATE;
+use super::{Square, SquareRoot};
+
+use core::iter::successors;
+
+pub const SPAWN_POINT: (f64, f64, f64) = (66649.54f64, -7418.37f64, 3118.52f64);
+pub const FINISH_HEIGHT: f64 = 373000f64;
+//Ticks the in-game timer has to stay unchanged to count as stopped, it only counts whole seconds
+const TIMER_STOPPED_TICKS: u32 = 2 * TICK_RATE as u32;
+//Ticks after reaching the region after which an ending activation fires even though the timer
+//did not stop
+const ENDING_FALLBACK_TICKS: u32 = 10 * TICK_RATE as u32;
+//How far above a height split its area still counts as the entry of the next area
+const ENTRY_DEPTH: f64 = 1000f64;
+
+#[derive(Clone, Copy)]
 pub struct SphereTrigger {
     position_x: f64,
     position_y: f64,
     position_z: f64,
-    radius_squared: f64,
+    radius: f64,
 }
 
 impl SphereTrigger {
-    pub fn new(
+    pub const fn new(
         position_x: f64,
         position_y: f64,
         position_z: f64,
-        radius_squared: f64,
+        radius: f64,
     ) -> SphereTrigger {
         SphereTrigger {
             position_x,
             position_y,
             position_z,
-            radius_squared,
+            radius,
         }
     }
-    pub fn should_split(&self, state: &State) -> bool {
-        let dist_squared = (self.position_x - state.position_x).square()
-            + (self.position_y - state.position_y).square()
-            + (self.position_z - state.position_z).square();
-        dist_squared <= self.radius_squared
+    pub fn distance(&self, (x, y, z): (f64, f64, f64)) -> f64 {
+        let dist_squared = (self.position_x - x).square()
+            + (self.position_y - y).square()
+            + (self.position_z - z).square();
+        (dist_squared.square_root() - self.radius).max(0f64)
+    }
+    pub const fn contains(&self, (x, y, z): (f64, f64, f64)) -> bool {
+        let dist_x = self.position_x - x;
+        let dist_y = self.position_y - y;
+        let dist_z = self.position_z - z;
+        dist_x * dist_x + dist_y * dist_y + dist_z * dist_z <= self.radius * self.radius
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct UpperSphereTrigger {
-    position_x: f64,
-    position_y: f64,
-    position_z: f64,
-    radius_squared: f64,
+    sphere: SphereTrigger,
 }
 
 impl UpperSphereTrigger {
-    pub fn new(
+    pub const fn new(
         position_x: f64,
         position_y: f64,
         position_z: f64,
-        radius_squared: f64,
+        radius: f64,
     ) -> UpperSphereTrigger {
         UpperSphereTrigger {
-            position_x,
-            position_y,
-            position_z,
-            radius_squared,
+            sphere: SphereTrigger::new(position_x, position_y, position_z, radius),
         }
     }
-    pub fn should_split(&self, state: &State) -> bool {
-        let dist_squared = (self.position_x - state.position_x).square()
-            + (self.position_y - state.position_y).square()
-            + (self.position_z - state.position_z).square();
-        dist_squared <= self.radius_squared && self.position_z <= state.position_z
+    pub const fn contains(&self, point: (f64, f64, f64)) -> bool {
+        self.sphere.contains(point) && self.sphere.position_z <= point.2
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct BoxTrigger {
     position_x_1: f64,
     position_y_1: f64,
@@ -69,7 +84,7 @@ pub struct BoxTrigger {
 }
 
 impl BoxTrigger {
-    pub fn new(
+    pub const fn new(
         position_x_1: f64,
         position_x_2: f64,
         position_y_1: f64,
@@ -82,251 +97,737 @@ impl BoxTrigger {
             position_y_2,
         }
     }
-
-    pub fn should_split(&self, state: &State) -> bool {
-        state.position_x >= self.position_x_1
-            && state.position_x <= self.position_x_2
-            && state.position_y >= self.position_y_1
-            && state.position_y <= self.position_y_2
+    pub fn distance(&self, (x, y, _): (f64, f64, f64)) -> f64 {
+        let dist_x = (self.position_x_1 - x).max(x - self.position_x_2).max(0f64);
+        let dist_y = (self.position_y_1 - y).max(y - self.position_y_2).max(0f64);
+        (dist_x.square() + dist_y.square()).square_root()
+    }
+    pub const fn contains(&self, (x, y, _): (f64, f64, f64)) -> bool {
+        x >= self.position_x_1
+            && x <= self.position_x_2
+            && y >= self.position_y_1
+            && y <= self.position_y_2
     }
 }
 
+#[derive(Clone, Copy)]
 pub struct HeightTrigger {
     position_z: f64,
 }
 
 impl HeightTrigger {
-    pub fn new(position_z: f64) -> HeightTrigger {
+    pub const fn new(position_z: f64) -> HeightTrigger {
         HeightTrigger { position_z }
     }
+    pub const fn contains(&self, (_, _, z): (f64, f64, f64)) -> bool {
+        z >= self.position_z
+    }
+}
+
+pub const SPAWN_AREA: Region = Region::Sphere(SphereTrigger::new(
+    SPAWN_POINT.0,
+    SPAWN_POINT.1,
+    SPAWN_POINT.2,
+    1000f64,
+));
+
+#[derive(Clone, Copy)]
+pub enum Region {
+    Sphere(SphereTrigger),
+    UpperSphere(UpperSphereTrigger),
+    Box(BoxTrigger),
+    Height(HeightTrigger),
+}
+
+impl Region {
+    pub fn distance(&self, point: (f64, f64, f64)) -> f64 {
+        match self {
+            Region::Sphere(sphere) => sphere.distance(point),
+            Region::UpperSphere(sphere) => sphere
+                .sphere
+                .distance(point)
+                .max(sphere.sphere.position_z - point.2),
+            Region::Box(area) => area.distance(point),
+            Region::Height(height) => (height.position_z - point.2).max(0f64),
+        }
+    }
+    pub const fn contains(&self, point: (f64, f64, f64)) -> bool {
+        match self {
+            Region::Sphere(sphere) => sphere.contains(point),
+            Region::UpperSphere(sphere) => sphere.contains(point),
+            Region::Box(area) => area.contains(point),
+            Region::Height(height) => height.contains(point),
+        }
+    }
     pub fn should_split(&self, state: &State) -> bool {
-        state.position_z >= self.position_z
+        self.contains((state.position_x, state.position_y, state.position_z))
+    }
+    //Lowest height at which the player can be inside the region
+    pub const fn floor(&self) -> f64 {
+        match self {
+            Region::Sphere(sphere) => sphere.position_z - sphere.radius,
+            Region::UpperSphere(sphere) => sphere.sphere.position_z,
+            Region::Height(height) => height.position_z,
+            Region::Box(_) => f64::NEG_INFINITY,
+        }
+    }
+    //Highest height at which the player can be inside the region
+    pub const fn ceiling(&self) -> f64 {
+        match self {
+            Region::Sphere(sphere) => sphere.position_z + sphere.radius,
+            Region::UpperSphere(sphere) => sphere.sphere.position_z + sphere.sphere.radius,
+            Region::Box(_) | Region::Height(_) => f64::INFINITY,
+        }
+    }
+}
+
+//How a trigger decides that its region has been completed
+#[derive(Clone, Copy)]
+pub enum Activation {
+    //On the first tick the player is inside
+    Enter,
+    //Once the player stayed inside for the given number of seconds
+    Dwell(u32),
+    //On the tick the player leaves after having been inside
+    Exit,
+    //Once the in-game timer stops after the player reached the region, which is when the ending
+    //starts, or a while after reaching it if the timer does not stop
+    Ending,
+}
+
+//Movement the player has to show, in units per second, on the tick a trigger fires
+#[derive(Clone, Copy)]
+pub enum Movement {
+    Any,
+    Rising(f64),
+    Travelling(f64),
+}
+
+impl Movement {
+    pub fn matches(&self, state: &State) -> bool {
+        match *self {
+            Movement::Any => true,
+            Movement::Rising(speed) => state.velocity_z >= speed,
+            Movement::Travelling(speed) => state.horizontal_speed_squared() >= speed.square(),
+        }
     }
 }
 
-#[derive(Clone)]
+#[derive(Gui, Clone, Copy, PartialEq)]
 pub enum Triggers {
+    /// Underworld
+    #[default]
     Underworld,
+    /// First Ladder
     FirstLadder,
+    /// Hell Cliffs
     HellCliffs,
+    /// Hell Cliffs Drone
     HellCliffsDrone,
+    /// The Car Race
     TheCarRace,
+    /// Rotating Cube
     RotatingCube,
+    /// The Whispering Vault
     TheWhisperingVault,
+    /// Open The Door
     OpenTheDoor,
+    /// The Aqua Maze
     TheAquaMaze,
+    /// The Subway Station
     TheSubwayStation,
+    /// Station Drone
     StationDrone,
+    /// Red Elevator to City
     RedElevatortoCity,
+    /// The City
     TheCity,
+    /// City Crane
     CityCrane,
+    /// Double Crane Platforms
     DoubleCranePlatforms,
+    /// Elevator to Buildings
     ElevatortoBuildings,
+    /// Over The Buildings
     OverTheBuildings,
+    /// Buildings Helicopter
     BuildingsHelicopter,
+    /// The Warehouse
     TheWarehouse,
+    /// The Harbor
     TheHarbor,
+    /// Blue Railing Chariot
     BlueRailingChariot,
+    /// Elevator to Stairs
     Elevatortostairs,
+    /// Helicopter to Temple
     HelicoptertoTemple,
+    /// The Temple
     TheTemple,
+    /// Elevator to Asian Shrine
     ElevatortoAsianShrine,
+    /// The Asian Shrine
     TheAsianShrine,
+    /// Wooden Horse Wagon
     WoodenHorseWagon,
+    /// The Deities
     TheDeities,
+    /// Wooden Boat
     WoodenBoat,
+    /// Zeus Lightning
     ZeusLightning,
+    /// Air Balloon Pump
     AirBalloonPump,
+    /// The Garden
     TheGarden,
+    /// The Final Trials
     TheFinalTrials,
+    /// Kiosk
     Kiosk,
+    /// Carriage
     Carriage,
+    /// The Sun
     TheSun,
 }
 
 impl Triggers {
-    pub fn should_split(&self, state: &State) -> bool {
-        match *self {
+    pub const COUNT: usize = Triggers::TheSun as usize + 1;
+
+    pub const ALL: [Triggers; Triggers::COUNT] = [
+        Triggers::Underworld,
+        Triggers::FirstLadder,
+        Triggers::HellCliffs,
+        Triggers::HellCliffsDrone,
+        Triggers::TheCarRace,
+        Triggers::RotatingCube,
+        Triggers::TheWhisperingVault,
+        Triggers::OpenTheDoor,
+        Triggers::TheAquaMaze,
+        Triggers::TheSubwayStation,
+        Triggers::StationDrone,
+        Triggers::RedElevatortoCity,
+        Triggers::TheCity,
+        Triggers::CityCrane,
+        Triggers::DoubleCranePlatforms,
+        Triggers::ElevatortoBuildings,
+        Triggers::OverTheBuildings,
+        Triggers::BuildingsHelicopter,
+        Triggers::TheWarehouse,
+        Triggers::TheHarbor,
+        Triggers::BlueRailingChariot,
+        Triggers::Elevatortostairs,
+        Triggers::HelicoptertoTemple,
+        Triggers::TheTemple,
+        Triggers::ElevatortoAsianShrine,
+        Triggers::TheAsianShrine,
+        Triggers::WoodenHorseWagon,
+        Triggers::TheDeities,
+        Triggers::WoodenBoat,
+        Triggers::ZeusLightning,
+        Triggers::AirBalloonPump,
+        Triggers::TheGarden,
+        Triggers::TheFinalTrials,
+        Triggers::Kiosk,
+        Triggers::Carriage,
+        Triggers::TheSun,
+    ];
+
+    pub const fn regions(&self) -> &'static [Region] {
+        match self {
             Triggers::Underworld => {
-                SphereTrigger::new(48169.70f64, -6670.38f64, 10415.32f64, 1600f64 * 1600f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        48169.70f64,
+                        -6670.38f64,
+                        10415.32f64,
+                        1600f64,
+                    ))]
+                }
             }
             Triggers::FirstLadder => {
-                BoxTrigger::new(57607f64, 57647f64, -4686f64, -4602f64).should_split(state)
+                const {
+                    &[Region::Box(BoxTrigger::new(
+                        57607f64, 57647f64, -4686f64, -4602f64,
+                    ))]
+                }
             }
             Triggers::HellCliffs => {
-                SphereTrigger::new(58518.04f64, -5896.68f64, 22762.73f64, 600f64 * 600f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        58518.04f64,
+                        -5896.68f64,
+                        22762.73f64,
+                        600f64,
+                    ))]
+                }
             }
             Triggers::HellCliffsDrone => {
-                SphereTrigger::new(63361.97f64, -7714.82f64, 28700f64, 150f64 * 150f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        63361.97f64,
+                        -7714.82f64,
+                        28700f64,
+                        150f64,
+                    ))]
+                }
             }
             Triggers::TheCarRace => {
-                SphereTrigger::new(60736f64, -5806f64, 34473f64, 2000f64 * 2000f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        60736f64, -5806f64, 34473f64, 2000f64,
+                    ))]
+                }
             }
             Triggers::RotatingCube => {
-                SphereTrigger::new(57840.65f64, -4092.85f64, 40061.93f64, 550f64 * 550f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        57840.65f64,
+                        -4092.85f64,
+                        40061.93f64,
+                        550f64,
+                    ))]
+                }
+            }
+            Triggers::TheWhisperingVault => {
+                const { &[Region::Height(HeightTrigger::new(47380f64))] }
             }
-            Triggers::TheWhisperingVault => HeightTrigger::new(47380f64).should_split(state),
             Triggers::OpenTheDoor => {
-                SphereTrigger::new(70099.82f64, -12091.44f64, 54309.28f64, 1000f64 * 1000f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        70099.82f64,
+                        -12091.44f64,
+                        54309.28f64,
+                        1000f64,
+                    ))]
+                }
             }
             Triggers::TheAquaMaze => {
-                SphereTrigger::new(56175f64, -9616f64, 61161f64, 1900f64 * 1900f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        56175f64, -9616f64, 61161f64, 1900f64,
+                    ))]
+                }
             }
             Triggers::TheSubwayStation => {
-                SphereTrigger::new(61164.22f64, -16430.15f64, 66112.40f64, 875f64 * 875f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        61164.22f64,
+                        -16430.15f64,
+                        66112.40f64,
+                        875f64,
+                    ))]
+                }
             }
             Triggers::StationDrone => {
-                SphereTrigger::new(43285.67f64, -19387.02f64, 71659.89f64, 85f64 * 85f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        43285.67f64,
+                        -19387.02f64,
+                        71659.89f64,
+                        85f64,
+                    ))]
+                }
             }
             Triggers::RedElevatortoCity => {
-                SphereTrigger::new(55934.05f64, -7474.58f64, 85888.98f64, 360f64 * 360f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        55934.05f64,
+                        -7474.58f64,
+                        85888.98f64,
+                        360f64,
+                    ))]
+                }
             }
-            Triggers::TheCity => HeightTrigger::new(114310f64).should_split(state),
+            Triggers::TheCity => const { &[Region::Height(HeightTrigger::new(114310f64))] },
             Triggers::CityCrane => {
-                SphereTrigger::new(64600f64, -5950f64, 119450f64, 2340f64 * 2340f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        64600f64, -5950f64, 119450f64, 2340f64,
+                    ))]
+                }
             }
             Triggers::DoubleCranePlatforms => {
-                SphereTrigger::new(54500f64, -9000f64, 125818f64, 2100f64 * 2100f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        54500f64, -9000f64, 125818f64, 2100f64,
+                    ))]
+                }
             }
             Triggers::ElevatortoBuildings => {
-                SphereTrigger::new(59656f64, -7845f64, 133400f64, 1050f64 * 1050f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        59656f64, -7845f64, 133400f64, 1050f64,
+                    ))]
+                }
+            }
+            Triggers::OverTheBuildings => {
+                const { &[Region::Height(HeightTrigger::new(139850f64))] }
             }
-            Triggers::OverTheBuildings => HeightTrigger::new(139850f64).should_split(state),
             Triggers::BuildingsHelicopter => {
-                SphereTrigger::new(61241f64, -7060f64, 149050f64, 150f64 * 150f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        61241f64, -7060f64, 149050f64, 150f64,
+                    ))]
+                }
             }
-            Triggers::TheWarehouse => HeightTrigger::new(155996f64).should_split(state),
+            Triggers::TheWarehouse => const { &[Region::Height(HeightTrigger::new(155996f64))] },
             Triggers::TheHarbor => {
-                UpperSphereTrigger::new(58833.19f64, -10168.21f64, 167476.00f64, 4100f64 * 4100f64)
-                    .should_split(state)
+                const {
+                    &[
+                        Region::UpperSphere(UpperSphereTrigger::new(
+                            58833.19f64,
+                            -10168.21f64,
+                            167476.00f64,
+                            4100f64,
+                        )),
+                        //Skips around the upper sphere still have to climb past its top
+                        Region::Height(HeightTrigger::new(171576f64)),
+                    ]
+                }
             }
             Triggers::BlueRailingChariot => {
-                SphereTrigger::new(55267f64, -9424f64, 175450f64, 2200f64 * 2200f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        55267f64, -9424f64, 175450f64, 2200f64,
+                    ))]
+                }
             }
             Triggers::Elevatortostairs => {
-                SphereTrigger::new(60015f64, -5540f64, 180200f64, 1550f64 * 1550f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        60015f64, -5540f64, 180200f64, 1550f64,
+                    ))]
+                }
             }
             Triggers::HelicoptertoTemple => {
-                SphereTrigger::new(63676.359f64, -12937.55f64, 203690f64, 150f64 * 150f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        63676.359f64,
+                        -12937.55f64,
+                        203690f64,
+                        150f64,
+                    ))]
+                }
             }
-            Triggers::TheTemple => HeightTrigger::new(209230f64).should_split(state),
+            Triggers::TheTemple => const { &[Region::Height(HeightTrigger::new(209230f64))] },
             Triggers::ElevatortoAsianShrine => {
-                SphereTrigger::new(60844f64, -13114f64, 233000f64, 1800f64 * 1800f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        60844f64, -13114f64, 233000f64, 1800f64,
+                    ))]
+                }
             }
-            Triggers::TheAsianShrine => HeightTrigger::new(244140f64).should_split(state),
+            Triggers::TheAsianShrine => const { &[Region::Height(HeightTrigger::new(244140f64))] },
             Triggers::WoodenHorseWagon => {
-                SphereTrigger::new(57636f64, -9231f64, 270700f64, 1300f64 * 1300f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        57636f64, -9231f64, 270700f64, 1300f64,
+                    ))]
+                }
             }
             Triggers::TheDeities => {
-                SphereTrigger::new(40703.73f64, -1902.68f64, 278480.62f64, 2270f64 * 2270f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        40703.73f64,
+                        -1902.68f64,
+                        278480.62f64,
+                        2270f64,
+                    ))]
+                }
             }
             Triggers::WoodenBoat => {
-                SphereTrigger::new(52432f64, -1512f64, 284710f64, 1600f64 * 1600f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        52432f64, -1512f64, 284710f64, 1600f64,
+                    ))]
+                }
             }
             Triggers::ZeusLightning => {
-                SphereTrigger::new(81930f64, -23160f64, 301710f64, 610f64 * 610f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        81930f64, -23160f64, 301710f64, 610f64,
+                    ))]
+                }
             }
             Triggers::AirBalloonPump => {
-                SphereTrigger::new(45515f64, -11171f64, 307250f64, 100f64 * 100f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        45515f64, -11171f64, 307250f64, 100f64,
+                    ))]
+                }
             }
             Triggers::TheGarden => {
-                SphereTrigger::new(47610.46f64, -11083.54f64, 334514.26f64, 4200f64 * 4200f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        47610.46f64,
+                        -11083.54f64,
+                        334514.26f64,
+                        4200f64,
+                    ))]
+                }
             }
             Triggers::TheFinalTrials => {
-                SphereTrigger::new(56907f64, -32509f64, 337997f64, 940f64 * 940f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        56907f64, -32509f64, 337997f64, 940f64,
+                    ))]
+                }
+            }
+            Triggers::Kiosk => {
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        56351f64, -23282f64, 351400f64, 650f64,
+                    ))]
+                }
             }
-            Triggers::Kiosk => SphereTrigger::new(56351f64, -23282f64, 351400f64, 650f64 * 650f64)
-                .should_split(state),
             Triggers::Carriage => {
-                SphereTrigger::new(60435f64, -27560f64, 357470f64, 450f64 * 450f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        60435f64, -27560f64, 357470f64, 450f64,
+                    ))]
+                }
             }
             Triggers::TheSun => {
-                SphereTrigger::new(89000f64, -4500f64, 373000f64, 8500f64 * 8500f64)
-                    .should_split(state)
+                const {
+                    &[Region::Sphere(SphereTrigger::new(
+                        89000f64,
+                        -4500f64,
+                        FINISH_HEIGHT,
+                        8500f64,
+                    ))]
+                }
             }
         }
     }
-    pub fn next_trigger_raw(&self) -> Triggers {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Triggers::Underworld => "Underworld",
+            Triggers::FirstLadder => "First Ladder",
+            Triggers::HellCliffs => "Hell Cliffs",
+            Triggers::HellCliffsDrone => "Hell Cliffs Drone",
+            Triggers::TheCarRace => "The Car Race",
+            Triggers::RotatingCube => "Rotating Cube",
+            Triggers::TheWhisperingVault => "The Whispering Vault",
+            Triggers::OpenTheDoor => "Open The Door",
+            Triggers::TheAquaMaze => "The Aqua Maze",
+            Triggers::TheSubwayStation => "The Subway Station",
+            Triggers::StationDrone => "Station Drone",
+            Triggers::RedElevatortoCity => "Red Elevator to City",
+            Triggers::TheCity => "The City",
+            Triggers::CityCrane => "City Crane",
+            Triggers::DoubleCranePlatforms => "Double Crane Platforms",
+            Triggers::ElevatortoBuildings => "Elevator to Buildings",
+            Triggers::OverTheBuildings => "Over The Buildings",
+            Triggers::BuildingsHelicopter => "Buildings Helicopter",
+            Triggers::TheWarehouse => "The Warehouse",
+            Triggers::TheHarbor => "The Harbor",
+            Triggers::BlueRailingChariot => "Blue Railing Chariot",
+            Triggers::Elevatortostairs => "Elevator to Stairs",
+            Triggers::HelicoptertoTemple => "Helicopter to Temple",
+            Triggers::TheTemple => "The Temple",
+            Triggers::ElevatortoAsianShrine => "Elevator to Asian Shrine",
+            Triggers::TheAsianShrine => "The Asian Shrine",
+            Triggers::WoodenHorseWagon => "Wooden Horse Wagon",
+            Triggers::TheDeities => "The Deities",
+            Triggers::WoodenBoat => "Wooden Boat",
+            Triggers::ZeusLightning => "Zeus Lightning",
+            Triggers::AirBalloonPump => "Air Balloon Pump",
+            Triggers::TheGarden => "The Garden",
+            Triggers::TheFinalTrials => "The Final Trials",
+            Triggers::Kiosk => "Kiosk",
+            Triggers::Carriage => "Carriage",
+            Triggers::TheSun => "The Sun",
+        }
+    }
+    //The areas of the main route, these are enabled by default
+    pub const fn is_main(&self) -> bool {
+        matches!(
+            self,
+            Triggers::Underworld
+                | Triggers::HellCliffs
+                | Triggers::TheCarRace
+                | Triggers::TheWhisperingVault
+                | Triggers::TheSubwayStation
+                | Triggers::TheCity
+                | Triggers::OverTheBuildings
+                | Triggers::TheWarehouse
+                | Triggers::TheHarbor
+                | Triggers::TheTemple
+                | Triggers::TheAsianShrine
+                | Triggers::TheDeities
+                | Triggers::TheGarden
+                | Triggers::TheSun
+        )
+    }
+    fn previous_main(&self) -> Option<Triggers> {
+        Triggers::ALL[..*self as usize]
+            .iter()
+            .rev()
+            .copied()
+            .find(Triggers::is_main)
+    }
+    //The main trigger that ends the area this trigger is in
+    pub fn area(&self) -> Triggers {
+        successors(Some(*self), Triggers::next_trigger_raw)
+            .find(Triggers::is_main)
+            .unwrap_or(Triggers::TheSun)
+    }
+    pub const fn floor(&self) -> f64 {
+        let regions = self.regions();
+        let mut floor = f64::INFINITY;
+        let mut i = 0;
+        while i < regions.len() {
+            floor = floor.min(regions[i].floor());
+            i += 1;
+        }
+        floor
+    }
+    pub const fn ceiling(&self) -> f64 {
+        let regions = self.regions();
+        let mut ceiling = f64::NEG_INFINITY;
+        let mut i = 0;
+        while i < regions.len() {
+            ceiling = ceiling.max(regions[i].ceiling());
+            i += 1;
+        }
+        ceiling
+    }
+    //How far the player is from the closest region of this trigger
+    pub fn distance(&self, state: &State) -> f64 {
+        self.regions()
+            .iter()
+            .map(|region| region.distance((state.position_x, state.position_y, state.position_z)))
+            .fold(f64::INFINITY, f64::min)
+    }
+    pub fn contains(&self, state: &State) -> bool {
+        self.regions()
+            .iter()
+            .any(|region| region.should_split(state))
+    }
+    pub fn activation(&self, settings: &Settings) -> Activation {
+        match self {
+            //No end of game flag is known in memory, the stopped timer marks the ending instead
+            //and the sphere is the fallback
+            Triggers::TheSun if settings.split_on_ending => Activation::Ending,
+            Triggers::BuildingsHelicopter => Activation::Dwell(2),
+            Triggers::HelicoptertoTemple => Activation::Exit,
+            Triggers::AirBalloonPump => Activation::Dwell(2),
+            _ => Activation::Enter,
+        }
+    }
+    //Only misc splits wait for the ride, the speeds are estimates and a main split that never
+    //fires would stall the route
+    pub fn movement(&self) -> Movement {
         match self {
-            Triggers::Underworld => Triggers::FirstLadder,
-            Triggers::FirstLadder => Triggers::HellCliffs,
-            Triggers::HellCliffs => Triggers::HellCliffsDrone,
-            Triggers::HellCliffsDrone => Triggers::TheCarRace,
-            Triggers::TheCarRace => Triggers::RotatingCube,
-            Triggers::RotatingCube => Triggers::TheWhisperingVault,
-            Triggers::TheWhisperingVault => Triggers::OpenTheDoor,
-            Triggers::OpenTheDoor => Triggers::TheAquaMaze,
-            Triggers::TheAquaMaze => Triggers::TheSubwayStation,
-            Triggers::TheSubwayStation => Triggers::StationDrone,
-            Triggers::StationDrone => Triggers::RedElevatortoCity,
-            Triggers::RedElevatortoCity => Triggers::TheCity,
-            Triggers::TheCity => Triggers::CityCrane,
-            Triggers::CityCrane => Triggers::DoubleCranePlatforms,
-            Triggers::DoubleCranePlatforms => Triggers::ElevatortoBuildings,
-            Triggers::ElevatortoBuildings => Triggers::OverTheBuildings,
-            Triggers::OverTheBuildings => Triggers::BuildingsHelicopter,
-            Triggers::BuildingsHelicopter => Triggers::TheWarehouse,
-            Triggers::TheWarehouse => Triggers::TheHarbor,
-            Triggers::TheHarbor => Triggers::BlueRailingChariot,
-            Triggers::BlueRailingChariot => Triggers::Elevatortostairs,
-            Triggers::Elevatortostairs => Triggers::HelicoptertoTemple,
-            Triggers::HelicoptertoTemple => Triggers::TheTemple,
-            Triggers::TheTemple => Triggers::ElevatortoAsianShrine,
-            Triggers::ElevatortoAsianShrine => Triggers::TheAsianShrine,
-            Triggers::TheAsianShrine => Triggers::WoodenHorseWagon,
-            Triggers::WoodenHorseWagon => Triggers::TheDeities,
-            Triggers::TheDeities => Triggers::WoodenBoat,
-            Triggers::WoodenBoat => Triggers::ZeusLightning,
-            Triggers::ZeusLightning => Triggers::AirBalloonPump,
-            Triggers::AirBalloonPump => Triggers::TheGarden,
-            Triggers::TheGarden => Triggers::TheFinalTrials,
-            Triggers::TheFinalTrials => Triggers::Kiosk,
-            Triggers::Kiosk => Triggers::Carriage,
-            Triggers::Carriage => Triggers::TheSun,
-            Triggers::TheSun => Triggers::TheSun,
+            Triggers::HelicoptertoTemple => Movement::Rising(200f64),
+            Triggers::WoodenHorseWagon => Movement::Travelling(500f64),
+            Triggers::WoodenBoat => Movement::Travelling(400f64),
+            _ => Movement::Any,
         }
     }
-    pub fn next_trigger(&self, settings: &Settings) -> Triggers {
-        let mut trigger = self.next_trigger_raw();
-        while !trigger.is_enabled(settings) {
-            trigger = trigger.next_trigger_raw();
+    pub const fn next_trigger_raw(&self) -> Option<Triggers> {
+        match self {
+            Triggers::Underworld => Some(Triggers::FirstLadder),
+            Triggers::FirstLadder => Some(Triggers::HellCliffs),
+            Triggers::HellCliffs => Some(Triggers::HellCliffsDrone),
+            Triggers::HellCliffsDrone => Some(Triggers::TheCarRace),
+            Triggers::TheCarRace => Some(Triggers::RotatingCube),
+            Triggers::RotatingCube => Some(Triggers::TheWhisperingVault),
+            Triggers::TheWhisperingVault => Some(Triggers::OpenTheDoor),
+            Triggers::OpenTheDoor => Some(Triggers::TheAquaMaze),
+            Triggers::TheAquaMaze => Some(Triggers::TheSubwayStation),
+            Triggers::TheSubwayStation => Some(Triggers::StationDrone),
+            Triggers::StationDrone => Some(Triggers::RedElevatortoCity),
+            Triggers::RedElevatortoCity => Some(Triggers::TheCity),
+            Triggers::TheCity => Some(Triggers::CityCrane),
+            Triggers::CityCrane => Some(Triggers::DoubleCranePlatforms),
+            Triggers::DoubleCranePlatforms => Some(Triggers::ElevatortoBuildings),
+            Triggers::ElevatortoBuildings => Some(Triggers::OverTheBuildings),
+            Triggers::OverTheBuildings => Some(Triggers::BuildingsHelicopter),
+            Triggers::BuildingsHelicopter => Some(Triggers::TheWarehouse),
+            Triggers::TheWarehouse => Some(Triggers::TheHarbor),
+            Triggers::TheHarbor => Some(Triggers::BlueRailingChariot),
+            Triggers::BlueRailingChariot => Some(Triggers::Elevatortostairs),
+            Triggers::Elevatortostairs => Some(Triggers::HelicoptertoTemple),
+            Triggers::HelicoptertoTemple => Some(Triggers::TheTemple),
+            Triggers::TheTemple => Some(Triggers::ElevatortoAsianShrine),
+            Triggers::ElevatortoAsianShrine => Some(Triggers::TheAsianShrine),
+            Triggers::TheAsianShrine => Some(Triggers::WoodenHorseWagon),
+            Triggers::WoodenHorseWagon => Some(Triggers::TheDeities),
+            Triggers::TheDeities => Some(Triggers::WoodenBoat),
+            Triggers::WoodenBoat => Some(Triggers::ZeusLightning),
+            Triggers::ZeusLightning => Some(Triggers::AirBalloonPump),
+            Triggers::AirBalloonPump => Some(Triggers::TheGarden),
+            Triggers::TheGarden => Some(Triggers::TheFinalTrials),
+            Triggers::TheFinalTrials => Some(Triggers::Kiosk),
+            Triggers::Kiosk => Some(Triggers::Carriage),
+            Triggers::Carriage => Some(Triggers::TheSun),
+            Triggers::TheSun => None,
         }
-        trigger
     }
-    pub fn first_trigger(settings: &Settings) -> Triggers {
-        let mut trigger = Triggers::Underworld;
-        while !trigger.is_enabled(settings) {
-            trigger = trigger.next_trigger_raw();
+    //None once every enabled trigger has been split, the route is complete then
+    pub fn next_trigger(&self, settings: &Settings) -> Option<Triggers> {
+        successors(self.next_trigger_raw(), Triggers::next_trigger_raw)
+            .find(|trigger| trigger.is_enabled(settings))
+    }
+    //Every enabled trigger after this one, in route order
+    pub fn upcoming<'a>(&self, settings: &'a Settings) -> impl Iterator<Item = Triggers> + 'a {
+        successors(self.next_trigger(settings), move |trigger| {
+            trigger.next_trigger(settings)
+        })
+    }
+    //The last enabled trigger before `current`, or before the end of the route once it is complete
+    pub fn previous_trigger(current: Option<Triggers>, settings: &Settings) -> Option<Triggers> {
+        let end = current.map_or(Triggers::COUNT, |trigger| trigger as usize);
+        Triggers::ALL[..end]
+            .iter()
+            .rev()
+            .copied()
+            .find(|trigger| trigger.is_enabled(settings))
+    }
+    //The trigger that ends the segment with the given index in the splits
+    pub fn nth_enabled(index: usize, settings: &Settings) -> Option<Triggers> {
+        Triggers::ALL
+            .iter()
+            .copied()
+            .filter(|trigger| trigger.is_enabled(settings))
+            .nth(index)
+    }
+    //Whether the player is still at the start of the area this main trigger ends, an
+    //individual level starts once they leave it
+    pub fn in_area_entry(&self, state: &State) -> bool {
+        match self.previous_main() {
+            None => SPAWN_AREA.should_split(state),
+            Some(previous) => previous.regions().iter().any(|region| match region {
+                //Height splits have no top, only the first stretch above them is the entry
+                Region::Height(height) => {
+                    height.contains((state.position_x, state.position_y, state.position_z))
+                        && state.position_z < height.position_z + ENTRY_DEPTH
+                }
+                region => region.should_split(state),
+            }),
         }
-        trigger
+    }
+    pub fn first_trigger(settings: &Settings) -> Option<Triggers> {
+        successors(Some(Triggers::Underworld), Triggers::next_trigger_raw)
+            .find(|trigger| trigger.is_enabled(settings))
     }
     pub fn is_enabled(&self, settings: &Settings) -> bool {
+        if settings.practice_mode {
+            return *self == settings.practice_end;
+        }
+        match settings.category {
+            Category::Custom => self.is_toggled(settings),
+            Category::AnyPercent => self.is_main(),
+            Category::AllSplits => true,
+            //The area's end, with the misc splits inside it that are toggled on
+            Category::IndividualLevel => {
+                let end = settings.individual_level_area.end_trigger();
+                *self == end || (self.area() == end && self.is_toggled(settings))
+            }
+        }
+    }
+    fn is_toggled(&self, settings: &Settings) -> bool {
         match self {
             Triggers::Underworld => settings.toggle_underworld,
             Triggers::FirstLadder => settings.toggle_first_ladder,
@@ -367,3 +868,116 @@ impl Triggers {
         }
     }
 }
+
+#[derive(Clone, Copy, Default)]
+struct Presence {
+    //Ticks in a row the player has been inside, the in-game timer only counts whole seconds
+    inside_ticks: u32,
+    //Ticks since the player first reached the region, the ending may move them out of it
+    reached_ticks: u32,
+    last_timer: u32,
+    timer_stopped_ticks: u32,
+}
+
+//Remembers per trigger how long the player has been inside, so dwell and exit activations can
+//be evaluated across ticks
+pub struct TriggerTracker {
+    presence: [Presence; Triggers::COUNT],
+}
+
+impl TriggerTracker {
+    pub fn new() -> TriggerTracker {
+        TriggerTracker {
+            presence: [Presence::default(); Triggers::COUNT],
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.presence = [Presence::default(); Triggers::COUNT];
+    }
+
+    //Keeps a trigger that fired but was not allowed to split ready to fire again, exit
+    //activations otherwise only fire on the one tick the player leaves
+    pub fn hold(&mut self, trigger: &Triggers) {
+        let presence = &mut self.presence[*trigger as usize];
+        presence.inside_ticks = presence.inside_ticks.max(1);
+    }
+
+    pub fn fired(&mut self, trigger: &Triggers, state: &State, settings: &Settings) -> bool {
+        let inside = trigger.contains(state);
+        let presence = &mut self.presence[*trigger as usize];
+        let was_inside = presence.inside_ticks != 0;
+
+        if inside {
+            presence.inside_ticks = presence.inside_ticks.saturating_add(1);
+        } else {
+            presence.inside_ticks = 0;
+        }
+        if inside || presence.reached_ticks != 0 {
+            presence.reached_ticks = presence.reached_ticks.saturating_add(1);
+        }
+
+        if presence.reached_ticks != 0 && state.timer == presence.last_timer {
+            presence.timer_stopped_ticks += 1;
+        } else {
+            presence.timer_stopped_ticks = 0;
+        }
+        presence.last_timer = state.timer;
+
+        let activated = match trigger.activation(settings) {
+            Activation::Enter => inside,
+            Activation::Dwell(seconds) => presence.inside_ticks >= seconds * TICK_RATE as u32,
+            Activation::Exit => was_inside && !inside,
+            Activation::Ending => {
+                presence.timer_stopped_ticks >= TIMER_STOPPED_TICKS
+                    || presence.reached_ticks >= ENDING_FALLBACK_TICKS
+            }
+        };
+        activated && trigger.movement().matches(state)
+    }
+}
+
+//Checks the route geometry at compile time so that mistakes fail the build instead of a run.
+//Every variant having a setting is already enforced by the exhaustive match in `is_toggled`.
+const _: () = {
+    let mut previous_main: Option<Triggers> = None;
+    let mut i = 0;
+    while i < Triggers::COUNT {
+        let trigger = Triggers::ALL[i];
+        assert!(
+            trigger as usize == i,
+            "Triggers::ALL must list the triggers in declaration order"
+        );
+        match trigger.next_trigger_raw() {
+            Some(next) => assert!(
+                next as usize == i + 1,
+                "next_trigger_raw must visit every trigger in route order"
+            ),
+            None => assert!(
+                i + 1 == Triggers::COUNT,
+                "only the last trigger may end the route"
+            ),
+        }
+
+        let regions = trigger.regions();
+        let mut j = 0;
+        while j < regions.len() {
+            assert!(
+                !regions[j].contains(SPAWN_POINT),
+                "a trigger region contains the spawn point"
+            );
+            j += 1;
+        }
+
+        if trigger.is_main() {
+            if let Some(previous) = previous_main {
+                assert!(
+                    previous.floor() < trigger.floor(),
+                    "main route triggers must get higher along the route"
+                );
+            }
+            previous_main = Some(trigger);
+        }
+        i += 1;
+    }
+};
@@ -0,0 +1,79 @@
+use super::state::State;
+use super::trigger::{Triggers, FINISH_HEIGHT, SPAWN_POINT};
+
+use core::fmt::{self, Write};
+
+//Fixed size text so variables can be formatted without an allocator, text that does not fit
+//is cut off
+pub struct Text<const N: usize> {
+    buffer: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> Text<N> {
+    pub fn new() -> Text<N> {
+        Text {
+            buffer: [0; N],
+            len: 0,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        core::str::from_utf8(&self.buffer[..self.len]).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> Write for Text<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let mut end = s.len().min(N - self.len);
+        while !s.is_char_boundary(end) {
+            end -= 1;
+        }
+        self.buffer[self.len..self.len + end].copy_from_slice(&s.as_bytes()[..end]);
+        self.len += end;
+        Ok(())
+    }
+}
+
+pub fn set(key: &str, value: fmt::Arguments) {
+    let mut text = Text::<128>::new();
+    let _ = text.write_fmt(value);
+    asr::timer::set_variable(key, text.as_str());
+}
+
+//Height, progress and next split of the current run, shown as variables on the layout
+#[derive(Default)]
+pub struct RunVariables {
+    max_height: f64,
+}
+
+impl RunVariables {
+    pub fn start_run(&mut self, state: &State) {
+        self.max_height = state.position_z;
+    }
+
+    pub fn update(&mut self, state: &State, next_trigger: Option<Triggers>) {
+        self.max_height = self.max_height.max(state.position_z);
+        let progress = (state.position_z - SPAWN_POINT.2) / (FINISH_HEIGHT - SPAWN_POINT.2);
+
+        set("Height", format_args!("{:.0}", state.position_z));
+        set("Max Height", format_args!("{:.0}", self.max_height));
+        set(
+            "Progress",
+            format_args!("{:.1}%", progress.clamp(0f64, 1f64) * 100f64),
+        );
+        match next_trigger {
+            Some(trigger) => {
+                set("Next Split", format_args!("{}", trigger.name()));
+                set(
+                    "Distance to Next Split",
+                    format_args!("{:.0}", trigger.distance(state)),
+                );
+            }
+            None => {
+                set("Next Split", format_args!("Done"));
+                set("Distance to Next Split", format_args!("0"));
+            }
+        }
+    }
+}